@@ -14,25 +14,207 @@ struct LintResult {
     kind: String,
     message: String,
     suggestions: Vec<String>,
+    actions: Vec<LintAction>,
 }
 
-/// Lint the given text and return a JSON array of issues.
+/// A structured edit a host can apply directly by splicing `text` into
+/// `[start, end)`, covering every `harper_core::linting::Suggestion`
+/// variant rather than only `ReplaceWith`. `text` is the
+/// replacement/insertion text, empty for `remove`. `start`/`end` are in the
+/// same position encoding as the enclosing `LintResult`, except for
+/// `insert_after`, whose span is the zero-width point right after the
+/// flagged span (`start == end == the span's end offset`) so splicing
+/// composes correctly instead of deleting the flagged text.
+#[derive(Serialize)]
+struct LintAction {
+    kind: String,
+    text: String,
+    start: usize,
+    end: usize,
+}
+
+/// Position encoding used for the `start`/`end` offsets of a [`LintResult`],
+/// mirroring the LSP `positionEncoding` negotiation: hosts agree on units up
+/// front rather than the server guessing.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum PositionEncoding {
+    Utf8,
+    Utf16,
+    Utf32,
+}
+
+impl PositionEncoding {
+    fn from_raw(encoding: u8) -> Self {
+        match encoding {
+            0 => PositionEncoding::Utf8,
+            2 => PositionEncoding::Utf32,
+            _ => PositionEncoding::Utf16,
+        }
+    }
+
+    /// Offset of `source[..char_index]` in this encoding's units.
+    fn offset(self, source: &[char], char_index: usize) -> usize {
+        match self {
+            PositionEncoding::Utf8 => source[..char_index].iter().map(|c| c.len_utf8()).sum(),
+            PositionEncoding::Utf16 => source[..char_index].iter().map(|c| c.len_utf16()).sum(),
+            PositionEncoding::Utf32 => char_index,
+        }
+    }
+}
+
+/// Lint the given text and return a JSON array of issues, with spans
+/// expressed in UTF-16 code units (matching Swift/Objective-C hosts that
+/// map them onto `NSString`/`NSRange`). Invalid UTF-8 input is handled as
+/// in `harper_lint_encoded`, with spans as byte offsets.
 /// Caller must free the returned string with `harper_free_string`.
 #[no_mangle]
 pub extern "C" fn harper_lint(text: *const c_char) -> *mut c_char {
+    harper_lint_encoded(text, 1)
+}
+
+/// Lint the given text and return a JSON array of issues, with spans
+/// expressed in the requested position encoding: `0` = UTF-8 byte offsets,
+/// `1` = UTF-16 code units (the convention LSP calls `utf-16`, and what
+/// `NSRange` expects), `2` = UTF-32/char indices. Unrecognized values fall
+/// back to UTF-16.
+///
+/// If `text` contains invalid UTF-8 (e.g. Latin-1 or a truncated buffer),
+/// it is decoded lossily rather than producing no diagnostics; spans are
+/// then reported as raw byte offsets into the original buffer regardless
+/// of `encoding`, since the buffer isn't valid text in any of those units.
+///
+/// This rebuilds the curated dictionary and lint group on every call, which
+/// dominates the cost of interactive use. Prefer `harper_linter_new` plus
+/// `harper_lint_with` when linting repeatedly.
+/// Caller must free the returned string with `harper_free_string`.
+#[no_mangle]
+pub extern "C" fn harper_lint_encoded(text: *const c_char, encoding: u8) -> *mut c_char {
     if text.is_null() {
         return to_c_string("[]");
     }
 
     let c_str = unsafe { CStr::from_ptr(text) };
-    let text_str = match c_str.to_str() {
-        Ok(s) => s,
-        Err(_) => return to_c_string("[]"),
-    };
+    let decoded = decode_c_str(c_str);
 
     let dictionary = Arc::new(FstDictionary::curated());
-    let document = Document::new_plain_english(text_str, &dictionary);
     let mut lint_group = LintGroup::new_curated(dictionary.clone(), Dialect::American);
+    to_c_string(&lint_json(
+        &dictionary,
+        &mut lint_group,
+        decoded.as_str(),
+        encoding,
+        decoded.byte_map(),
+    ))
+}
+
+/// Text recovered from a C string, plus (when the input wasn't valid UTF-8)
+/// a map from each decoded `char`'s index back to its byte offset in the
+/// original buffer, plus a final entry for the buffer's end.
+enum DecodedText<'a> {
+    Exact(&'a str),
+    Lossy(String, Vec<usize>),
+}
+
+impl DecodedText<'_> {
+    fn as_str(&self) -> &str {
+        match self {
+            DecodedText::Exact(s) => s,
+            DecodedText::Lossy(s, _) => s,
+        }
+    }
+
+    fn byte_map(&self) -> Option<&[usize]> {
+        match self {
+            DecodedText::Exact(_) => None,
+            DecodedText::Lossy(_, map) => Some(map),
+        }
+    }
+}
+
+fn decode_c_str(c_str: &CStr) -> DecodedText<'_> {
+    match c_str.to_str() {
+        Ok(s) => DecodedText::Exact(s),
+        Err(_) => {
+            let (decoded, byte_starts) = lossy_decode_with_byte_map(c_str.to_bytes());
+            DecodedText::Lossy(decoded, byte_starts)
+        }
+    }
+}
+
+/// Lossily decode `bytes` as UTF-8, and also return, for each `char` in the
+/// decoded string, the byte offset in `bytes` where that char began, followed
+/// by one final entry equal to `bytes.len()` (so an end index maps too). Unlike
+/// `String::from_utf8_lossy` (which can emit one replacement char per
+/// maximal invalid subsequence), every *consecutive run* of invalid bytes
+/// here — regardless of how many maximal invalid subsequences it contains —
+/// collapses to a single U+FFFD and a single map entry (pointing at the
+/// run's first byte), so the cursor still advances past all of it without
+/// shifting later spans.
+fn lossy_decode_with_byte_map(bytes: &[u8]) -> (String, Vec<usize>) {
+    let mut decoded = String::new();
+    let mut byte_starts = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match std::str::from_utf8(&bytes[i..]) {
+            Ok(valid) => {
+                for (offset, ch) in valid.char_indices() {
+                    byte_starts.push(i + offset);
+                    decoded.push(ch);
+                }
+                break;
+            }
+            Err(err) => {
+                let valid_len = err.valid_up_to();
+                if valid_len > 0 {
+                    let valid = std::str::from_utf8(&bytes[i..i + valid_len]).unwrap();
+                    for (offset, ch) in valid.char_indices() {
+                        byte_starts.push(i + offset);
+                        decoded.push(ch);
+                    }
+                    i += valid_len;
+                }
+
+                let run_start = i;
+                i += err.error_len().unwrap_or(bytes.len() - i).max(1);
+
+                // Swallow any further consecutive invalid bytes into this run.
+                while i < bytes.len() {
+                    match std::str::from_utf8(&bytes[i..]) {
+                        Ok(_) => break,
+                        Err(next_err) if next_err.valid_up_to() == 0 => {
+                            i += next_err.error_len().unwrap_or(bytes.len() - i).max(1);
+                        }
+                        Err(_) => break,
+                    }
+                }
+
+                byte_starts.push(run_start);
+                decoded.push(char::REPLACEMENT_CHARACTER);
+            }
+        }
+    }
+
+    byte_starts.push(bytes.len());
+    (decoded, byte_starts)
+}
+
+/// Run `lint_group` over `text_str` and render the results as the JSON
+/// string shared by both the stateless and handle-based entry points.
+///
+/// `byte_map`, when present, overrides `encoding` entirely and reports spans
+/// as offsets into the original (pre-lossy-decode) byte buffer; see
+/// `lossy_decode_with_byte_map`.
+fn lint_json(
+    dictionary: &Arc<FstDictionary>,
+    lint_group: &mut LintGroup<Arc<FstDictionary>>,
+    text_str: &str,
+    encoding: u8,
+    byte_map: Option<&[usize]>,
+) -> String {
+    let encoding = PositionEncoding::from_raw(encoding);
+
+    let document = Document::new_plain_english(text_str, dictionary);
     let lints = lint_group.lint(&document);
 
     let source: Vec<char> = text_str.chars().collect();
@@ -47,9 +229,12 @@ pub extern "C" fn harper_lint(text: *const c_char) -> *mut c_char {
             continue;
         }
 
-        // Convert char span to byte offsets for NSRange (UTF-16 length) compatibility
-        let start_byte: usize = source[..span.start].iter().collect::<String>().len();
-        let end_byte: usize = source[..span.end].iter().collect::<String>().len();
+        let offset_of = |char_index: usize| match byte_map {
+            Some(map) => map[char_index],
+            None => encoding.offset(&source, char_index),
+        };
+        let start_offset = offset_of(span.start);
+        let end_offset = offset_of(span.end);
 
         let word: String = source[span.start..span.end].iter().collect();
 
@@ -73,20 +258,134 @@ pub extern "C" fn harper_lint(text: *const c_char) -> *mut c_char {
             })
             .collect();
 
+        let actions: Vec<LintAction> = lint
+            .suggestions
+            .iter()
+            .map(|s| {
+                let (kind, text, start, end) = match s {
+                    harper_core::linting::Suggestion::ReplaceWith(chars) => (
+                        "replace",
+                        chars.iter().collect::<String>(),
+                        start_offset,
+                        end_offset,
+                    ),
+                    harper_core::linting::Suggestion::Remove => {
+                        ("remove", String::new(), start_offset, end_offset)
+                    }
+                    harper_core::linting::Suggestion::InsertAfter(chars) => (
+                        "insert_after",
+                        chars.iter().collect::<String>(),
+                        // Zero-width, at the point after the flagged span: splicing `text`
+                        // into `[end_offset, end_offset)` inserts without touching the
+                        // original words, unlike `replace`/`remove` which consume the span.
+                        end_offset,
+                        end_offset,
+                    ),
+                    _ => ("unknown", String::new(), start_offset, end_offset),
+                };
+                LintAction {
+                    kind: kind.to_string(),
+                    text,
+                    start,
+                    end,
+                }
+            })
+            .collect();
+
         let message = lint.message.clone();
 
         results.push(LintResult {
             word,
-            start: start_byte,
-            end: end_byte,
+            start: start_offset,
+            end: end_offset,
             kind: kind_lower,
             message,
             suggestions,
+            actions,
         });
     }
 
-    let json = serde_json::to_string(&results).unwrap_or_else(|_| "[]".to_string());
-    to_c_string(&json)
+    serde_json::to_string(&results).unwrap_or_else(|_| "[]".to_string())
+}
+
+/// Opaque handle pairing a curated dictionary with a reusable lint group, so
+/// hosts that lint repeatedly (e.g. on every keystroke) pay the dictionary
+/// construction cost once instead of on every call.
+///
+/// Not thread-safe: use one handle per thread, or guard a shared handle with
+/// a mutex on the host side.
+pub struct HarperLinter {
+    dictionary: Arc<FstDictionary>,
+    lint_group: LintGroup<Arc<FstDictionary>>,
+}
+
+fn dialect_from_raw(dialect: u8) -> Dialect {
+    match dialect {
+        1 => Dialect::British,
+        2 => Dialect::Australian,
+        3 => Dialect::Canadian,
+        _ => Dialect::American,
+    }
+}
+
+/// Create a new linter handle for the given dialect (`0=American`,
+/// `1=British`, `2=Australian`, `3=Canadian`; unrecognized values fall back
+/// to American). The curated dictionary is built once and amortized across
+/// every `harper_lint_with` call on this handle.
+/// Caller must free the handle with `harper_linter_free`.
+#[no_mangle]
+pub extern "C" fn harper_linter_new(dialect: u8) -> *mut HarperLinter {
+    let dictionary = Arc::new(FstDictionary::curated());
+    let lint_group = LintGroup::new_curated(dictionary.clone(), dialect_from_raw(dialect));
+    Box::into_raw(Box::new(HarperLinter {
+        dictionary,
+        lint_group,
+    }))
+}
+
+/// Free a linter handle created by `harper_linter_new`.
+#[no_mangle]
+pub extern "C" fn harper_linter_free(linter: *mut HarperLinter) {
+    if !linter.is_null() {
+        unsafe {
+            let _ = Box::from_raw(linter);
+        }
+    }
+}
+
+/// Lint `text` using an existing handle, with spans in UTF-16 code units
+/// (byte offsets for invalid UTF-8 input, as in `harper_lint_encoded`).
+/// Caller must free the returned string with `harper_free_string`.
+#[no_mangle]
+pub extern "C" fn harper_lint_with(linter: *mut HarperLinter, text: *const c_char) -> *mut c_char {
+    harper_lint_with_encoded(linter, text, 1)
+}
+
+/// Lint `text` using an existing handle, with spans in the requested
+/// position encoding (see `harper_lint_encoded` for the encoding values and
+/// for how invalid UTF-8 input is handled).
+/// Caller must free the returned string with `harper_free_string`.
+#[no_mangle]
+pub extern "C" fn harper_lint_with_encoded(
+    linter: *mut HarperLinter,
+    text: *const c_char,
+    encoding: u8,
+) -> *mut c_char {
+    if linter.is_null() || text.is_null() {
+        return to_c_string("[]");
+    }
+
+    let c_str = unsafe { CStr::from_ptr(text) };
+    let decoded = decode_c_str(c_str);
+
+    let linter = unsafe { &mut *linter };
+    to_c_string(&lint_json(
+        &linter.dictionary,
+        &mut linter.lint_group,
+        decoded.as_str(),
+        encoding,
+        decoded.byte_map(),
+    ))
 }
 
 /// Free a string returned by `harper_lint`.
@@ -102,3 +401,59 @@ pub extern "C" fn harper_free_string(ptr: *mut c_char) {
 fn to_c_string(s: &str) -> *mut c_char {
     CString::new(s).unwrap_or_default().into_raw()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lossy_decode_single_invalid_byte_mid_string() {
+        let (decoded, byte_map) = lossy_decode_with_byte_map(b"ab\xFFcd");
+        assert_eq!(decoded, "ab\u{FFFD}cd");
+        assert_eq!(byte_map, vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn lossy_decode_collapses_adjacent_invalid_subsequences_into_one_run() {
+        // `from_utf8_lossy` would report 0xFF and 0xFE as two separate
+        // maximal invalid subsequences (two replacement chars); this
+        // function collapses the whole run into a single one.
+        let (decoded, byte_map) = lossy_decode_with_byte_map(&[0xFF, 0xFE]);
+        assert_eq!(decoded, "\u{FFFD}");
+        assert_eq!(byte_map, vec![0, 2]);
+    }
+
+    #[test]
+    fn lossy_decode_truncated_multibyte_sequence_at_eof() {
+        // `\xE2\x82` starts a 3-byte sequence but is cut off before the
+        // final continuation byte, so `error_len()` is `None`.
+        let (decoded, byte_map) = lossy_decode_with_byte_map(b"abc\xE2\x82");
+        assert_eq!(decoded, "abc\u{FFFD}");
+        assert_eq!(byte_map, vec![0, 1, 2, 3, 5]);
+    }
+
+    #[test]
+    fn lossy_decode_all_valid_string_matches_cumulative_utf8_lengths() {
+        let (decoded, byte_map) = lossy_decode_with_byte_map("héllo".as_bytes());
+        assert_eq!(decoded, "héllo");
+        assert_eq!(byte_map, vec![0, 1, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn position_encoding_offset_astral_char_differs_per_encoding() {
+        // '😀' is one `char` but two UTF-16 units and four UTF-8 bytes —
+        // the exact motivating example from the encoding request.
+        let source: Vec<char> = "a😀b".chars().collect();
+        let after_emoji = 2;
+
+        assert_eq!(
+            PositionEncoding::Utf8.offset(&source, after_emoji),
+            1 + '😀'.len_utf8()
+        );
+        assert_eq!(
+            PositionEncoding::Utf16.offset(&source, after_emoji),
+            1 + '😀'.len_utf16()
+        );
+        assert_eq!(PositionEncoding::Utf32.offset(&source, after_emoji), 2);
+    }
+}